@@ -7,10 +7,59 @@ pub use der_parser::oid::Oid;
 use netsnmp_sys_nocrypto as netsnmp_sys;
 #[cfg(feature = "dynamic")]
 use once_cell::sync::OnceCell;
+
+#[cfg(not(feature = "dynamic"))]
+mod session;
+#[cfg(not(feature = "dynamic"))]
+pub use session::{
+    AuthProtocol, PrivProtocol, SecurityLevel, Session, SessionConfig, Transport, V3Auth, Varbind,
+    Version, Walk,
+};
+
+#[cfg(not(feature = "dynamic"))]
+mod trap;
+#[cfg(not(feature = "dynamic"))]
+pub use trap::{Trap, TrapListener};
+
+#[cfg(not(feature = "dynamic"))]
+mod mib;
+#[cfg(not(feature = "dynamic"))]
+pub use mib::{
+    children, node_info, node_info_by_name, parent, Access, MibNode, Status, Syntax,
+};
+
 use std::env;
 use std::ffi::{CStr, CString};
 use std::fmt;
 use std::os::raw::c_char;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+
+/// Serializes access to net-snmp's non-reentrant global MIB tree.
+///
+/// net-snmp's parser and the `snprint_objid` / `get_node` / `get_tree` lookups
+/// all read (and, for `load_mib` / `add_mib_dir`, mutate) one process-wide tree
+/// that has no internal locking. Every translation entry point — `get_name`,
+/// `get_oid` and the `node_info` family — unconditionally takes a shared read
+/// lock for the duration of the lookup; there is no lock-free path. Concurrent
+/// lookups still run in parallel under the read lock, while MIB (re)loading
+/// (`load_mib` / `add_mib_dir`) takes the exclusive write lock.
+pub(crate) static SNMP_LOCK: RwLock<()> = RwLock::new(());
+
+/// Set once [`init`] has succeeded, so a second call errors instead of
+/// re-running the library initialization.
+static INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+/// Fail with [`ErrorKind::NotInitialized`] unless [`init`] has run.
+#[cfg(not(feature = "dynamic"))]
+#[inline]
+pub(crate) fn ensure_initialized() -> Result<(), Error> {
+    if INITIALIZED.load(Ordering::SeqCst) {
+        Ok(())
+    } else {
+        Err(Error::not_initialized())
+    }
+}
 
 #[cfg(feature = "dynamic")]
 static NETSNMP: OnceCell<libloading::Library> = OnceCell::new();
@@ -19,11 +68,24 @@ const MAX_OID_LEN: usize = 128;
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 pub enum ErrorKind {
+    /// A generic failure with no more specific classification.
     Failed,
+    /// Input supplied by the caller could not be parsed or converted.
     InvalidData,
+    /// The requested object is not present in the loaded MIB tree.
+    NotFound,
+    /// An OID is longer than net-snmp's `MAX_OID_LEN`.
+    OidTooLong,
+    /// A translation entry point was called before [`init`].
+    NotInitialized,
+    /// The net-snmp shared library could not be loaded (dynamic mode).
+    LibLoad,
+    /// A net-snmp call failed and reported the wrapped return code
+    /// (e.g. `snmp_errno` or a zero `get_node` result).
+    Ffi(i32),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Error {
     kind: ErrorKind,
     message: String,
@@ -47,9 +109,71 @@ impl Error {
         }
     }
     #[inline]
+    pub fn not_found(msg: impl fmt::Display) -> Self {
+        Self {
+            kind: ErrorKind::NotFound,
+            message: msg.to_string(),
+        }
+    }
+    #[inline]
+    pub fn oid_too_long() -> Self {
+        Self {
+            kind: ErrorKind::OidTooLong,
+            message: "SNMP OID too long".to_owned(),
+        }
+    }
+    #[inline]
+    pub fn not_initialized() -> Self {
+        Self {
+            kind: ErrorKind::NotInitialized,
+            message: "net-snmp library not initialized".to_owned(),
+        }
+    }
+    #[inline]
+    pub fn lib_load(msg: impl fmt::Display) -> Self {
+        Self {
+            kind: ErrorKind::LibLoad,
+            message: msg.to_string(),
+        }
+    }
+    #[inline]
+    pub fn ffi(code: i32, msg: impl fmt::Display) -> Self {
+        Self {
+            kind: ErrorKind::Ffi(code),
+            message: msg.to_string(),
+        }
+    }
+    #[inline]
     pub fn kind(&self) -> ErrorKind {
         self.kind
     }
+    /// The underlying net-snmp return code, when the error originated from a
+    /// failing library call ([`ErrorKind::Ffi`]).
+    #[inline]
+    pub fn code(&self) -> Option<i32> {
+        match self.kind {
+            ErrorKind::Ffi(code) => Some(code),
+            _ => None,
+        }
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        let message = match kind {
+            ErrorKind::Failed => "operation failed",
+            ErrorKind::InvalidData => "invalid data",
+            ErrorKind::NotFound => "not found",
+            ErrorKind::OidTooLong => "SNMP OID too long",
+            ErrorKind::NotInitialized => "net-snmp library not initialized",
+            ErrorKind::LibLoad => "unable to load net-snmp library",
+            ErrorKind::Ffi(_) => "net-snmp call failed",
+        };
+        Self {
+            kind,
+            message: message.to_owned(),
+        }
+    }
 }
 
 impl fmt::Display for Error {
@@ -109,6 +233,15 @@ impl<'a> Config<'a> {
     }
 }
 
+/// Initialize the net-snmp library and load the configured MIBs.
+///
+/// This is idempotent: the first successful call performs initialization and
+/// any call after it returns [`ErrorKind::Failed`] rather than re-running it
+/// (net-snmp's `init_snmp` is not safe to call twice). A call that fails is
+/// rolled back, so a later call tries again. Initialization takes the exclusive
+/// parser lock, so it is safe to race against translation calls from other
+/// threads.
+///
 /// # Safety
 ///
 /// Should not have safety problems unless netsnmp bugs are found
@@ -117,6 +250,22 @@ impl<'a> Config<'a> {
 ///
 /// Will panic if app_name contains a zero-char
 pub fn init(config: &Config) -> Result<(), Error> {
+    if INITIALIZED
+        .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+        .is_err()
+    {
+        return Err(Error::failed("snmptools already initialized"));
+    }
+    let _guard = SNMP_LOCK.write().unwrap_or_else(|e| e.into_inner());
+    if let Err(e) = init_locked(config) {
+        // Roll back so a fixed configuration can be retried.
+        INITIALIZED.store(false, Ordering::SeqCst);
+        return Err(e);
+    }
+    Ok(())
+}
+
+fn init_locked(config: &Config) -> Result<(), Error> {
     if !config.mibs.is_empty() {
         env::set_var("MIBS", config.mibs.join(":"));
     }
@@ -129,11 +278,13 @@ pub fn init(config: &Config) -> Result<(), Error> {
         if config.lib_path.is_empty() {
             return Err(Error::failed("lib path not set"));
         }
-        let lib = libloading::Library::new(config.lib_path).map_err(Error::failed)?;
+        let lib = libloading::Library::new(config.lib_path).map_err(Error::lib_load)?;
         let init: libloading::Symbol<unsafe extern "C" fn(name: *const c_char)> =
-            lib.get(b"init_snmp").map_err(Error::failed)?;
+            lib.get(b"init_snmp").map_err(Error::lib_load)?;
         init(app_name.as_ptr());
-        NETSNMP.set(lib).unwrap();
+        NETSNMP
+            .set(lib)
+            .map_err(|_| Error::failed("snmptools already initialized"))?;
     }
     #[cfg(not(feature = "dynamic"))]
     {
@@ -144,14 +295,57 @@ pub fn init(config: &Config) -> Result<(), Error> {
     Ok(())
 }
 
-/// # Safety
+/// Load an additional MIB file at runtime (wraps net-snmp's `read_mib`).
 ///
-/// Should not have safety problems unless netsnmp bugs are found
+/// Unlike setting `MIBS` before [`init`], this can extend the loaded set after
+/// startup. It takes the exclusive parser lock for the duration of the parse.
+#[cfg(not(feature = "dynamic"))]
+pub fn load_mib(path: &str) -> Result<(), Error> {
+    if !INITIALIZED.load(Ordering::SeqCst) {
+        return Err(Error::not_initialized());
+    }
+    let c_path = CString::new(path).map_err(Error::invalid_data)?;
+    let _guard = SNMP_LOCK.write().unwrap_or_else(|e| e.into_inner());
+    let tree = unsafe { netsnmp_sys::read_mib(c_path.as_ptr()) };
+    if tree.is_null() {
+        return Err(Error::failed(format!("unable to load MIB `{}`", path)));
+    }
+    Ok(())
+}
+
+/// Add a directory to net-snmp's MIB search path and (re)load every MIB in it
+/// (wraps `add_mibdir` + `read_all_mibs`).
 ///
-/// # Panics
+/// Like [`load_mib`], this takes the exclusive parser lock and may be called
+/// after [`init`] instead of pre-setting `MIBDIRS`.
+#[cfg(not(feature = "dynamic"))]
+pub fn add_mib_dir(dir: &str) -> Result<(), Error> {
+    if !INITIALIZED.load(Ordering::SeqCst) {
+        return Err(Error::not_initialized());
+    }
+    let c_dir = CString::new(dir).map_err(Error::invalid_data)?;
+    let _guard = SNMP_LOCK.write().unwrap_or_else(|e| e.into_inner());
+    let added = unsafe { netsnmp_sys::add_mibdir(c_dir.as_ptr()) };
+    if added < 0 {
+        return Err(Error::ffi(added, "unable to add MIB directory"));
+    }
+    unsafe {
+        netsnmp_sys::read_all_mibs();
+    }
+    Ok(())
+}
+
+/// Returns [`ErrorKind::NotInitialized`] if called before [`init`]. Takes the
+/// shared parser read lock for the duration of the lookup.
 ///
-/// Will panic if not initialized
+/// # Safety
+///
+/// Should not have safety problems unless netsnmp bugs are found
 pub fn get_name(snmp_oid: &Oid) -> Result<String, Error> {
+    if !INITIALIZED.load(Ordering::SeqCst) {
+        return Err(Error::not_initialized());
+    }
+    let _guard = SNMP_LOCK.read().unwrap_or_else(|e| e.into_inner());
     #[cfg(not(feature = "dynamic"))]
     const MAX_OID_LEN: usize = netsnmp_sys::MAX_OID_LEN;
 
@@ -163,7 +357,7 @@ pub fn get_name(snmp_oid: &Oid) -> Result<String, Error> {
     let mut n_len = 0;
     for (n, val) in snmp_oid.iter_bigint().enumerate() {
         if n > MAX_OID_LEN {
-            return Err(Error::invalid_data("SNMP OID too long"));
+            return Err(Error::oid_too_long());
         }
         n_oid[n] = val
             .try_into()
@@ -181,7 +375,7 @@ pub fn get_name(snmp_oid: &Oid) -> Result<String, Error> {
                 objid: *const u64,
                 objidlen: usize,
             ),
-        > = lib.get(b"snprint_objid").map_err(Error::failed)?;
+        > = lib.get(b"snprint_objid").map_err(Error::lib_load)?;
         snprint_objid(
             name_buf.as_mut_ptr(),
             MAX_NAME_LEN,
@@ -202,14 +396,17 @@ pub fn get_name(snmp_oid: &Oid) -> Result<String, Error> {
     Ok(name.to_string_lossy().to_string())
 }
 
+/// Returns [`ErrorKind::NotInitialized`] if called before [`init`]. Takes the
+/// shared parser read lock for the duration of the lookup.
+///
 /// # Safety
 ///
 /// Should not have safety problems unless netsnmp bugs are found
-///
-/// # Panics
-///
-/// Will panic if not initialized
 pub fn get_oid(name: &str) -> Result<Oid, Error> {
+    if !INITIALIZED.load(Ordering::SeqCst) {
+        return Err(Error::not_initialized());
+    }
+    let _guard = SNMP_LOCK.read().unwrap_or_else(|e| e.into_inner());
     #[cfg(not(feature = "dynamic"))]
     const MAX_OID_LEN: usize = netsnmp_sys::MAX_OID_LEN;
 
@@ -225,13 +422,13 @@ pub fn get_oid(name: &str) -> Result<Oid, Error> {
         let lib = NETSNMP.get().unwrap();
         let get_node: libloading::Symbol<
             unsafe extern "C" fn(name: *const c_char, oid: *mut u64, oid_len: *mut usize) -> i32,
-        > = lib.get(b"get_node").map_err(Error::failed)?;
+        > = lib.get(b"get_node").map_err(Error::lib_load)?;
         get_node(c_name.as_ptr(), n_oid.as_mut_ptr(), &mut len)
     };
     #[cfg(not(feature = "dynamic"))]
     let res = unsafe { netsnmp_sys::get_node(c_name.as_ptr(), n_oid.as_mut_ptr(), &mut len) };
     if res == 0 {
-        Err(Error::failed("Unable to get SNMP OID"))
+        Err(Error::ffi(res, "Unable to get SNMP OID"))
     } else {
         #[allow(clippy::unnecessary_cast)]
         Oid::from(&n_oid[..len].iter().map(|v| *v as u64).collect::<Vec<u64>>())
@@ -239,6 +436,37 @@ pub fn get_oid(name: &str) -> Result<Oid, Error> {
     }
 }
 
+/// Convert an [`Oid`] into net-snmp's raw `oid` array representation.
+///
+/// Returns the populated buffer together with its length. Used by the session
+/// and trap subsystems to build PDUs.
+#[cfg(not(feature = "dynamic"))]
+pub(crate) fn oid_to_raw(
+    snmp_oid: &Oid,
+) -> Result<([netsnmp_sys::oid; netsnmp_sys::MAX_OID_LEN], usize), Error> {
+    let mut n_oid: [netsnmp_sys::oid; netsnmp_sys::MAX_OID_LEN] =
+        [0; netsnmp_sys::MAX_OID_LEN];
+    let mut n_len = 0;
+    for (n, val) in snmp_oid.iter_bigint().enumerate() {
+        if n >= netsnmp_sys::MAX_OID_LEN {
+            return Err(Error::oid_too_long());
+        }
+        n_oid[n] = val
+            .try_into()
+            .map_err(|e| Error::failed(format!("Invalid SNMP OID: {}", e)))?;
+        n_len += 1;
+    }
+    Ok((n_oid, n_len))
+}
+
+/// Build an [`Oid`] from net-snmp's raw `oid` slice.
+#[cfg(not(feature = "dynamic"))]
+pub(crate) fn raw_to_oid(raw: &[netsnmp_sys::oid]) -> Result<Oid, Error> {
+    #[allow(clippy::unnecessary_cast)]
+    Oid::from(&raw.iter().map(|v| *v as u64).collect::<Vec<u64>>())
+        .map_err(|_| Error::failed("Unable to create SNMP OID"))
+}
+
 #[cfg(test)]
 mod test {
     use super::{get_name, get_oid, init, Config, Oid};
@@ -0,0 +1,241 @@
+//! MIB tree introspection: semantic metadata net-snmp already parsed.
+//!
+//! [`node_info`] maps net-snmp's `struct tree` (located with `get_tree`) into a
+//! Rust [`MibNode`] carrying the object's syntax, access, status, description
+//! and enumerated values, while [`children`] and [`parent`] walk the tree
+//! pointers. This lets callers render human-readable values and validate SET
+//! payloads against the declared syntax.
+
+use crate::{get_oid, oid_to_raw, Error, Oid};
+use netsnmp_sys_nocrypto as netsnmp_sys;
+use std::ffi::CStr;
+
+/// The abstract syntax of a MIB object, derived from the tree `type` field.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Syntax {
+    Other,
+    ObjectId,
+    OctetString,
+    Integer,
+    NetAddress,
+    IpAddress,
+    Counter,
+    Gauge,
+    TimeTicks,
+    Opaque,
+    Null,
+    Counter64,
+    Bits,
+    Unsigned32,
+}
+
+impl Syntax {
+    fn from_raw(ty: i32) -> Self {
+        match ty {
+            netsnmp_sys::TYPE_OBJID => Syntax::ObjectId,
+            netsnmp_sys::TYPE_OCTETSTR => Syntax::OctetString,
+            netsnmp_sys::TYPE_INTEGER => Syntax::Integer,
+            netsnmp_sys::TYPE_NETADDR => Syntax::NetAddress,
+            netsnmp_sys::TYPE_IPADDR => Syntax::IpAddress,
+            netsnmp_sys::TYPE_COUNTER => Syntax::Counter,
+            netsnmp_sys::TYPE_GAUGE => Syntax::Gauge,
+            netsnmp_sys::TYPE_TIMETICKS => Syntax::TimeTicks,
+            netsnmp_sys::TYPE_OPAQUE => Syntax::Opaque,
+            netsnmp_sys::TYPE_NULL => Syntax::Null,
+            netsnmp_sys::TYPE_COUNTER64 => Syntax::Counter64,
+            netsnmp_sys::TYPE_BITSTRING => Syntax::Bits,
+            netsnmp_sys::TYPE_UNSIGNED32 => Syntax::Unsigned32,
+            _ => Syntax::Other,
+        }
+    }
+}
+
+/// The MAX-ACCESS clause of a MIB object.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Access {
+    Other,
+    ReadOnly,
+    ReadWrite,
+    WriteOnly,
+    NoAccess,
+    NotifyOnly,
+    Create,
+}
+
+impl Access {
+    fn from_raw(access: i32) -> Self {
+        match access {
+            netsnmp_sys::MIB_ACCESS_READONLY => Access::ReadOnly,
+            netsnmp_sys::MIB_ACCESS_READWRITE => Access::ReadWrite,
+            netsnmp_sys::MIB_ACCESS_WRITEONLY => Access::WriteOnly,
+            netsnmp_sys::MIB_ACCESS_NOACCESS => Access::NoAccess,
+            netsnmp_sys::MIB_ACCESS_NOTIFY => Access::NotifyOnly,
+            netsnmp_sys::MIB_ACCESS_CREATE => Access::Create,
+            _ => Access::Other,
+        }
+    }
+}
+
+/// The STATUS clause of a MIB object.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Status {
+    Other,
+    Mandatory,
+    Optional,
+    Obsolete,
+    Deprecated,
+    Current,
+}
+
+impl Status {
+    fn from_raw(status: i32) -> Self {
+        match status {
+            netsnmp_sys::MIB_STATUS_MANDATORY => Status::Mandatory,
+            netsnmp_sys::MIB_STATUS_OPTIONAL => Status::Optional,
+            netsnmp_sys::MIB_STATUS_OBSOLETE => Status::Obsolete,
+            netsnmp_sys::MIB_STATUS_DEPRECATED => Status::Deprecated,
+            netsnmp_sys::MIB_STATUS_CURRENT => Status::Current,
+            _ => Status::Other,
+        }
+    }
+}
+
+/// Parsed metadata for a single MIB node.
+#[derive(Debug, Clone)]
+pub struct MibNode {
+    pub label: String,
+    pub oid: Oid,
+    pub syntax: Syntax,
+    pub access: Access,
+    pub status: Status,
+    pub description: Option<String>,
+    pub hint: Option<String>,
+    pub units: Option<String>,
+    /// Enumerated `(value, label)` pairs for INTEGER types with named values.
+    pub enums: Vec<(i64, String)>,
+    /// Index object labels for table rows.
+    pub index_names: Vec<String>,
+}
+
+/// Look up MIB metadata for `snmp_oid`.
+pub fn node_info(snmp_oid: &Oid) -> Result<MibNode, Error> {
+    crate::ensure_initialized()?;
+    let _guard = crate::SNMP_LOCK.read().unwrap_or_else(|e| e.into_inner());
+    let (raw, len) = oid_to_raw(snmp_oid)?;
+    unsafe {
+        let tree = netsnmp_sys::get_tree(raw.as_ptr(), len, netsnmp_sys::get_tree_head());
+        if tree.is_null() {
+            return Err(Error::not_found("OID not present in loaded MIBs"));
+        }
+        node_from_tree(tree, snmp_oid.clone())
+    }
+}
+
+/// Look up MIB metadata for a symbolic name (e.g. `"IF-MIB::ifInOctets"`).
+#[inline]
+pub fn node_info_by_name(name: &str) -> Result<MibNode, Error> {
+    node_info(&get_oid(name)?)
+}
+
+/// The immediate children of `snmp_oid` in the MIB tree.
+pub fn children(snmp_oid: &Oid) -> Result<Vec<MibNode>, Error> {
+    crate::ensure_initialized()?;
+    let _guard = crate::SNMP_LOCK.read().unwrap_or_else(|e| e.into_inner());
+    let (raw, len) = oid_to_raw(snmp_oid)?;
+    unsafe {
+        let tree = netsnmp_sys::get_tree(raw.as_ptr(), len, netsnmp_sys::get_tree_head());
+        if tree.is_null() {
+            return Err(Error::not_found("OID not present in loaded MIBs"));
+        }
+        let mut out = Vec::new();
+        let mut child = (*tree).child_list;
+        while !child.is_null() {
+            let oid = child_oid(snmp_oid, (*child).subid)?;
+            out.push(node_from_tree(child, oid)?);
+            child = (*child).next_peer;
+        }
+        Ok(out)
+    }
+}
+
+/// The parent of `snmp_oid` in the MIB tree, if any.
+pub fn parent(snmp_oid: &Oid) -> Result<Option<MibNode>, Error> {
+    crate::ensure_initialized()?;
+    let _guard = crate::SNMP_LOCK.read().unwrap_or_else(|e| e.into_inner());
+    let (raw, len) = oid_to_raw(snmp_oid)?;
+    unsafe {
+        let tree = netsnmp_sys::get_tree(raw.as_ptr(), len, netsnmp_sys::get_tree_head());
+        if tree.is_null() {
+            return Err(Error::not_found("OID not present in loaded MIBs"));
+        }
+        let parent = (*tree).parent;
+        if parent.is_null() {
+            return Ok(None);
+        }
+        // The parent OID is this node's OID minus its final sub-identifier.
+        let mut ids: Vec<_> = snmp_oid.iter_bigint().collect();
+        ids.pop();
+        let parent_oid = Oid::from(
+            &ids.iter()
+                .map(|v| u64::try_from(v.clone()).unwrap_or(0))
+                .collect::<Vec<u64>>(),
+        )
+        .map_err(|_| Error::failed("Unable to create parent OID"))?;
+        Ok(Some(node_from_tree(parent, parent_oid)?))
+    }
+}
+
+unsafe fn node_from_tree(
+    tree: *mut netsnmp_sys::tree,
+    oid: Oid,
+) -> Result<MibNode, Error> {
+    let label = c_str((*tree).label).unwrap_or_default();
+    let mut enums = Vec::new();
+    let mut e = (*tree).enums;
+    while !e.is_null() {
+        if let Some(name) = c_str((*e).label) {
+            enums.push((i64::from((*e).value), name));
+        }
+        e = (*e).next;
+    }
+    let mut index_names = Vec::new();
+    let mut idx = (*tree).indexes;
+    while !idx.is_null() {
+        if let Some(name) = c_str((*idx).ilabel) {
+            index_names.push(name);
+        }
+        idx = (*idx).next;
+    }
+    Ok(MibNode {
+        label,
+        oid,
+        syntax: Syntax::from_raw((*tree).type_ as i32),
+        access: Access::from_raw((*tree).access as i32),
+        status: Status::from_raw((*tree).status as i32),
+        description: c_str((*tree).description),
+        hint: c_str((*tree).hint),
+        units: c_str((*tree).units),
+        enums,
+        index_names,
+    })
+}
+
+/// Build a child OID by appending `subid` to the parent OID.
+fn child_oid(parent: &Oid, subid: netsnmp_sys::oid) -> Result<Oid, Error> {
+    let mut ids: Vec<u64> = parent
+        .iter_bigint()
+        .map(|v| u64::try_from(v).unwrap_or(0))
+        .collect();
+    #[allow(clippy::unnecessary_cast)]
+    ids.push(subid as u64);
+    Oid::from(&ids).map_err(|_| Error::failed("Unable to create child OID"))
+}
+
+#[inline]
+unsafe fn c_str(ptr: *const std::os::raw::c_char) -> Option<String> {
+    if ptr.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(ptr).to_string_lossy().into_owned())
+    }
+}
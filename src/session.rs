@@ -0,0 +1,580 @@
+//! SNMP client sessions: GET / GETNEXT / GETBULK / WALK against remote agents.
+//!
+//! A [`Session`] wraps net-snmp's single-session API (`snmp_sess_open` /
+//! `snmp_sess_synch_response`) so that several sessions can coexist without
+//! sharing the library's global session list. Build one through
+//! [`SessionConfig`] and drive it with [`Session::get`], [`Session::get_next`],
+//! [`Session::get_bulk`] or the [`Session::walk`] iterator.
+
+use crate::{oid_to_raw, raw_to_oid, Error, Oid};
+use netsnmp_sys_nocrypto as netsnmp_sys;
+use std::ffi::CString;
+use std::collections::VecDeque;
+use std::net::{IpAddr, Ipv4Addr};
+use std::os::raw::{c_int, c_uint, c_void};
+use std::ptr;
+use std::time::Duration;
+
+/// SNMP protocol version.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Version {
+    V1,
+    V2c,
+    V3,
+}
+
+/// Transport used to reach the agent.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Transport {
+    Udp,
+    Tcp,
+}
+
+impl Transport {
+    #[inline]
+    fn prefix(self) -> &'static str {
+        match self {
+            Transport::Udp => "udp",
+            Transport::Tcp => "tcp",
+        }
+    }
+}
+
+/// SNMPv3 security level.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SecurityLevel {
+    NoAuthNoPriv,
+    AuthNoPriv,
+    AuthPriv,
+}
+
+/// SNMPv3 authentication protocol.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum AuthProtocol {
+    Md5,
+    Sha,
+}
+
+/// SNMPv3 privacy protocol.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PrivProtocol {
+    Des,
+    Aes,
+}
+
+/// SNMPv3 user-based security block.
+#[derive(Debug, Clone)]
+pub struct V3Auth {
+    pub security_name: String,
+    pub level: SecurityLevel,
+    pub auth_protocol: AuthProtocol,
+    pub auth_passphrase: String,
+    pub priv_protocol: PrivProtocol,
+    pub priv_passphrase: String,
+}
+
+/// Builder describing how to open a [`Session`].
+#[derive(Debug, Clone)]
+pub struct SessionConfig {
+    host: String,
+    port: u16,
+    transport: Transport,
+    version: Version,
+    community: String,
+    timeout: Duration,
+    retries: u32,
+    v3: Option<V3Auth>,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_owned(),
+            port: 161,
+            transport: Transport::Udp,
+            version: Version::V2c,
+            community: "public".to_owned(),
+            timeout: Duration::from_secs(1),
+            retries: 5,
+            v3: None,
+        }
+    }
+}
+
+impl SessionConfig {
+    /// Start a configuration for the given peer. Accepted forms are a bare
+    /// host/address (`"host"`, `"10.0.0.1"`, `"::1"`), a `host:port` pair
+    /// (`"host:161"`, `"10.0.0.1:161"`), or a bracketed IPv6 literal with an
+    /// optional port (`"[::1]"`, `"[::1]:161"`). The port defaults to 161 when
+    /// omitted; a suffix that is not a valid `u16` port is treated as part of a
+    /// bare IPv6 address, never silently dropped.
+    #[inline]
+    pub fn new(peer: &str) -> Self {
+        let mut config = Self::default();
+        if let Some(rest) = peer.strip_prefix('[') {
+            // Bracketed IPv6: `[addr]` or `[addr]:port`.
+            if let Some((host, tail)) = rest.split_once(']') {
+                config.host = host.to_owned();
+                if let Some(port) = tail.strip_prefix(':').and_then(|p| p.parse().ok()) {
+                    config.port = port;
+                } else if !tail.is_empty() {
+                    // Not a valid `:port` suffix: keep the input verbatim.
+                    config.host = peer.to_owned();
+                }
+            } else {
+                config.host = peer.to_owned();
+            }
+        } else if let Some((host, port)) = peer
+            .rsplit_once(':')
+            .filter(|(host, _)| !host.contains(':'))
+            .and_then(|(host, port)| port.parse().ok().map(|port: u16| (host, port)))
+        {
+            // A single colon with a numeric suffix is `host:port`; anything else
+            // (including a bare `::1`) is taken verbatim as the host.
+            config.host = host.to_owned();
+            config.port = port;
+        } else {
+            config.host = peer.to_owned();
+        }
+        config
+    }
+    #[inline]
+    pub fn transport(mut self, transport: Transport) -> Self {
+        self.transport = transport;
+        self
+    }
+    #[inline]
+    pub fn version(mut self, version: Version) -> Self {
+        self.version = version;
+        self
+    }
+    #[inline]
+    pub fn community(mut self, community: &str) -> Self {
+        self.community = community.to_owned();
+        self
+    }
+    #[inline]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+    #[inline]
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+    /// Attach an SNMPv3 security block, implicitly switching to [`Version::V3`].
+    #[inline]
+    pub fn v3(mut self, v3: V3Auth) -> Self {
+        self.version = Version::V3;
+        self.v3 = Some(v3);
+        self
+    }
+
+    #[inline]
+    fn peer(&self) -> String {
+        format!("{}:{}:{}", self.transport.prefix(), self.host, self.port)
+    }
+
+    /// Open the session against the configured agent.
+    pub fn open(&self) -> Result<Session, Error> {
+        Session::open(self)
+    }
+}
+
+/// A decoded SNMP variable binding value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Varbind {
+    Integer(i64),
+    Counter32(u32),
+    Counter64(u64),
+    Gauge(u32),
+    TimeTicks(u32),
+    OctetString(Vec<u8>),
+    Oid(Oid),
+    IpAddress(IpAddr),
+    NoSuchObject,
+    NoSuchInstance,
+    EndOfMibView,
+    Null,
+}
+
+/// An open SNMP session to a single agent.
+pub struct Session {
+    handle: *mut c_void,
+}
+
+// The session handle is only ever touched behind `&mut self`, so it is safe to
+// move a `Session` across threads. net-snmp's single-session API is re-entrant
+// per handle.
+unsafe impl Send for Session {}
+
+impl Session {
+    fn open(config: &SessionConfig) -> Result<Session, Error> {
+        let peer = CString::new(config.peer()).map_err(Error::invalid_data)?;
+        let community = CString::new(config.community.as_str()).map_err(Error::invalid_data)?;
+        // The v3 security name must outlive `snmp_sess_open` (which `strdup`s its
+        // own copy); keep it in this scope so we free it rather than leak it.
+        let sec_name = match (config.version, &config.v3) {
+            (Version::V3, Some(v3)) => {
+                Some(CString::new(v3.security_name.as_str()).map_err(Error::invalid_data)?)
+            }
+            _ => None,
+        };
+        let handle = unsafe {
+            let mut session: netsnmp_sys::snmp_session = std::mem::zeroed();
+            netsnmp_sys::snmp_sess_init(&mut session);
+            session.peername = peer.as_ptr() as *mut _;
+            session.version = match config.version {
+                Version::V1 => netsnmp_sys::SNMP_VERSION_1,
+                Version::V2c => netsnmp_sys::SNMP_VERSION_2c,
+                Version::V3 => netsnmp_sys::SNMP_VERSION_3,
+            };
+            session.timeout = i64::try_from(config.timeout.as_micros())
+                .map_err(|_| Error::invalid_data("timeout too large"))?;
+            session.retries = c_int::try_from(config.retries)
+                .map_err(|_| Error::invalid_data("retries too large"))?;
+            if config.version == Version::V3 {
+                let v3 = config
+                    .v3
+                    .as_ref()
+                    .ok_or_else(|| Error::invalid_data("v3 auth block missing"))?;
+                let name = sec_name
+                    .as_ref()
+                    .ok_or_else(|| Error::invalid_data("v3 auth block missing"))?;
+                configure_v3(&mut session, v3, name)?;
+            } else {
+                session.community = community.as_ptr() as *mut u8;
+                session.community_len = config.community.len();
+            }
+            netsnmp_sys::snmp_sess_open(&mut session)
+        };
+        if handle.is_null() {
+            return Err(Error::ffi(snmp_errno(), "snmp_sess_open failed"));
+        }
+        Ok(Session { handle })
+    }
+
+    /// Issue a GET for the given OIDs and return one [`Varbind`] per request.
+    pub fn get(&mut self, oids: &[Oid]) -> Result<Vec<Varbind>, Error> {
+        Ok(self
+            .request(netsnmp_sys::SNMP_MSG_GET, oids, 0, 0)?
+            .into_iter()
+            .map(|(_, v)| v)
+            .collect())
+    }
+
+    /// Issue a GETNEXT for the given OIDs, returning the `(Oid, Varbind)` pairs
+    /// reported by the agent.
+    pub fn get_next(&mut self, oids: &[Oid]) -> Result<Vec<(Oid, Varbind)>, Error> {
+        self.request(netsnmp_sys::SNMP_MSG_GETNEXT, oids, 0, 0)
+    }
+
+    /// Issue a GETBULK, treating the first `non_repeaters` OIDs as scalars and
+    /// requesting up to `max_repetitions` rows for the rest.
+    pub fn get_bulk(
+        &mut self,
+        non_repeaters: u32,
+        max_repetitions: u32,
+        oids: &[Oid],
+    ) -> Result<Vec<(Oid, Varbind)>, Error> {
+        self.request(
+            netsnmp_sys::SNMP_MSG_GETBULK,
+            oids,
+            non_repeaters,
+            max_repetitions,
+        )
+    }
+
+    /// Walk the subtree rooted at `root`, yielding each `(Oid, Varbind)` pair.
+    ///
+    /// The iterator repeatedly issues GETNEXT until it leaves the subtree or the
+    /// agent returns `endOfMibView`. Call [`Walk::bulk`] to switch to GETBULK for
+    /// fewer round-trips against v2c/v3 agents.
+    #[inline]
+    pub fn walk<'a>(&'a mut self, root: &Oid) -> Walk<'a> {
+        Walk {
+            session: self,
+            root: root.clone(),
+            next: root.clone(),
+            bulk: None,
+            pending: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    fn request(
+        &mut self,
+        pdu_type: c_int,
+        oids: &[Oid],
+        non_repeaters: u32,
+        max_repetitions: u32,
+    ) -> Result<Vec<(Oid, Varbind)>, Error> {
+        // Encode every OID before creating the PDU, so a conversion error can
+        // never leak an allocated-but-unsent PDU.
+        let raw_oids = oids
+            .iter()
+            .map(oid_to_raw)
+            .collect::<Result<Vec<_>, _>>()?;
+        unsafe {
+            let pdu = netsnmp_sys::snmp_pdu_create(pdu_type);
+            if pdu.is_null() {
+                return Err(Error::failed("snmp_pdu_create failed"));
+            }
+            if pdu_type == netsnmp_sys::SNMP_MSG_GETBULK {
+                (*pdu).errstat = non_repeaters as i64;
+                (*pdu).errindex = max_repetitions as i64;
+            }
+            for (raw, len) in &raw_oids {
+                netsnmp_sys::snmp_add_null_var(pdu, raw.as_ptr(), *len);
+            }
+            let mut response: *mut netsnmp_sys::snmp_pdu = ptr::null_mut();
+            let status = netsnmp_sys::snmp_sess_synch_response(self.handle, pdu, &mut response);
+            Self::collect(status, response)
+        }
+    }
+
+    /// Turn a synchronous response into decoded `(Oid, Varbind)` pairs, freeing
+    /// the response PDU.
+    unsafe fn collect(
+        status: c_int,
+        response: *mut netsnmp_sys::snmp_pdu,
+    ) -> Result<Vec<(Oid, Varbind)>, Error> {
+        if status != netsnmp_sys::STAT_SUCCESS || response.is_null() {
+            if !response.is_null() {
+                netsnmp_sys::snmp_free_pdu(response);
+            }
+            return Err(Error::ffi(status, "snmp request failed"));
+        }
+        if (*response).errstat != netsnmp_sys::SNMP_ERR_NOERROR as i64 {
+            let code = (*response).errstat as i32;
+            netsnmp_sys::snmp_free_pdu(response);
+            return Err(Error::ffi(code, "agent reported an error"));
+        }
+        let mut out = Vec::new();
+        let mut var = (*response).variables;
+        while !var.is_null() {
+            let name = std::slice::from_raw_parts((*var).name, (*var).name_length);
+            let pair = match raw_to_oid(name) {
+                Ok(oid) => decode_varbind(var).map(|value| (oid, value)),
+                Err(e) => Err(e),
+            };
+            match pair {
+                Ok(pair) => out.push(pair),
+                Err(e) => {
+                    netsnmp_sys::snmp_free_pdu(response);
+                    return Err(e);
+                }
+            }
+            var = (*var).next_variable;
+        }
+        netsnmp_sys::snmp_free_pdu(response);
+        Ok(out)
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        if !self.handle.is_null() {
+            unsafe {
+                netsnmp_sys::snmp_sess_close(self.handle);
+            }
+        }
+    }
+}
+
+/// Iterator returned by [`Session::walk`].
+pub struct Walk<'a> {
+    session: &'a mut Session,
+    root: Oid,
+    next: Oid,
+    bulk: Option<u32>,
+    pending: VecDeque<(Oid, Varbind)>,
+    done: bool,
+}
+
+impl Walk<'_> {
+    /// Issue GETBULK instead of GETNEXT, requesting up to `max_repetitions` rows
+    /// per round-trip. Only meaningful against v2c/v3 agents.
+    #[inline]
+    pub fn bulk(mut self, max_repetitions: u32) -> Self {
+        self.bulk = Some(max_repetitions);
+        self
+    }
+}
+
+impl Iterator for Walk<'_> {
+    type Item = Result<(Oid, Varbind), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(pair) = self.pending.pop_front() {
+                return Some(Ok(pair));
+            }
+            if self.done {
+                return None;
+            }
+            let root = std::slice::from_ref(&self.next);
+            let reply = match self.bulk {
+                Some(max_repetitions) => self.session.get_bulk(0, max_repetitions, root),
+                None => self.session.get_next(root),
+            };
+            let reply = match reply {
+                Ok(reply) => reply,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            };
+            if reply.is_empty() {
+                self.done = true;
+                return None;
+            }
+            // Buffer every row still inside the subtree; stop as soon as one
+            // leaves it or the agent signals end-of-view.
+            for (oid, value) in reply {
+                if value == Varbind::EndOfMibView || !oid_within(&self.root, &oid) {
+                    self.done = true;
+                    break;
+                }
+                self.next = oid.clone();
+                self.pending.push_back((oid, value));
+            }
+        }
+    }
+}
+
+#[inline]
+fn oid_within(root: &Oid, candidate: &Oid) -> bool {
+    let root: Vec<_> = root.iter_bigint().collect();
+    let candidate: Vec<_> = candidate.iter_bigint().collect();
+    candidate.len() >= root.len() && candidate[..root.len()] == root[..]
+}
+
+pub(crate) unsafe fn decode_varbind(var: *const netsnmp_sys::variable_list) -> Result<Varbind, Error> {
+    use netsnmp_sys as ns;
+    let ty = (*var).r#type;
+    let val = (*var).val;
+    let len = (*var).val_len;
+    Ok(match ty as i32 {
+        x if x == ns::ASN_INTEGER as i32 => Varbind::Integer(*val.integer as i64),
+        x if x == ns::ASN_COUNTER as i32 => Varbind::Counter32(*val.integer as u32),
+        x if x == ns::ASN_GAUGE as i32 => Varbind::Gauge(*val.integer as u32),
+        x if x == ns::ASN_TIMETICKS as i32 => Varbind::TimeTicks(*val.integer as u32),
+        x if x == ns::ASN_COUNTER64 as i32 => {
+            let c = &*val.counter64;
+            Varbind::Counter64((u64::from(c.high) << 32) | u64::from(c.low))
+        }
+        x if x == ns::ASN_OCTET_STR as i32 => {
+            Varbind::OctetString(std::slice::from_raw_parts(val.string, len).to_vec())
+        }
+        x if x == ns::ASN_OBJECT_ID as i32 => {
+            let slice = std::slice::from_raw_parts(val.objid, len / std::mem::size_of::<ns::oid>());
+            Varbind::Oid(raw_to_oid(slice)?)
+        }
+        x if x == ns::ASN_IPADDRESS as i32 => {
+            let octets = std::slice::from_raw_parts(val.string, len);
+            if octets.len() == 4 {
+                Varbind::IpAddress(IpAddr::V4(Ipv4Addr::new(
+                    octets[0], octets[1], octets[2], octets[3],
+                )))
+            } else {
+                return Err(Error::invalid_data("malformed IpAddress varbind"));
+            }
+        }
+        x if x == ns::SNMP_NOSUCHOBJECT as i32 => Varbind::NoSuchObject,
+        x if x == ns::SNMP_NOSUCHINSTANCE as i32 => Varbind::NoSuchInstance,
+        x if x == ns::SNMP_ENDOFMIBVIEW as i32 => Varbind::EndOfMibView,
+        x if x == ns::ASN_NULL as i32 => Varbind::Null,
+        other => return Err(Error::invalid_data(format!("unsupported ASN type {}", other))),
+    })
+}
+
+unsafe fn configure_v3(
+    session: &mut netsnmp_sys::snmp_session,
+    v3: &V3Auth,
+    name: &CString,
+) -> Result<(), Error> {
+    session.securityLevel = match v3.level {
+        SecurityLevel::NoAuthNoPriv => netsnmp_sys::SNMP_SEC_LEVEL_NOAUTH,
+        SecurityLevel::AuthNoPriv => netsnmp_sys::SNMP_SEC_LEVEL_AUTHNOPRIV,
+        SecurityLevel::AuthPriv => netsnmp_sys::SNMP_SEC_LEVEL_AUTHPRIV,
+    };
+    // Borrowed from `sec_name` in `open`, which outlives `snmp_sess_open`.
+    session.securityName = name.as_ptr() as *mut _;
+    session.securityNameLen = v3.security_name.len();
+
+    // net-snmp does not derive localized keys on its own: the caller must point
+    // `securityAuthProto`/`securityPrivProto` at the protocol OIDs and run
+    // `generate_Ku` into `securityAuthKey`/`securityPrivKey`.
+    let needs_auth = matches!(
+        v3.level,
+        SecurityLevel::AuthNoPriv | SecurityLevel::AuthPriv
+    );
+    if needs_auth {
+        let (proto, proto_len) = match v3.auth_protocol {
+            AuthProtocol::Md5 => (
+                netsnmp_sys::usmHMACMD5AuthProtocol.as_ptr(),
+                netsnmp_sys::usmHMACMD5AuthProtocol.len(),
+            ),
+            AuthProtocol::Sha => (
+                netsnmp_sys::usmHMACSHA1AuthProtocol.as_ptr(),
+                netsnmp_sys::usmHMACSHA1AuthProtocol.len(),
+            ),
+        };
+        session.securityAuthProto = proto as *mut _;
+        session.securityAuthProtoLen = proto_len;
+        session.securityAuthKeyLen = session.securityAuthKey.len();
+        generate_ku(proto, proto_len, &v3.auth_passphrase, {
+            let key = session.securityAuthKey.as_mut_ptr();
+            (key, &mut session.securityAuthKeyLen)
+        })?;
+
+        if v3.level == SecurityLevel::AuthPriv {
+            let priv_proto = match v3.priv_protocol {
+                PrivProtocol::Des => netsnmp_sys::usmDESPrivProtocol.as_ptr(),
+                PrivProtocol::Aes => netsnmp_sys::usmAESPrivProtocol.as_ptr(),
+            };
+            let priv_proto_len = match v3.priv_protocol {
+                PrivProtocol::Des => netsnmp_sys::usmDESPrivProtocol.len(),
+                PrivProtocol::Aes => netsnmp_sys::usmAESPrivProtocol.len(),
+            };
+            session.securityPrivProto = priv_proto as *mut _;
+            session.securityPrivProtoLen = priv_proto_len;
+            session.securityPrivKeyLen = session.securityPrivKey.len();
+            // The privacy key is derived with the *auth* hash, per USM.
+            generate_ku(proto, proto_len, &v3.priv_passphrase, {
+                let key = session.securityPrivKey.as_mut_ptr();
+                (key, &mut session.securityPrivKeyLen)
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Run net-snmp's `generate_Ku` to localize `passphrase` against the given hash
+/// protocol OID, writing the key into `(buf, len)`.
+unsafe fn generate_ku(
+    proto: *const netsnmp_sys::oid,
+    proto_len: usize,
+    passphrase: &str,
+    (buf, len): (*mut u8, &mut usize),
+) -> Result<(), Error> {
+    let rc = netsnmp_sys::generate_Ku(
+        proto,
+        proto_len as c_uint,
+        passphrase.as_ptr(),
+        passphrase.len(),
+        buf,
+        len,
+    );
+    if rc != netsnmp_sys::SNMPERR_SUCCESS {
+        return Err(Error::ffi(rc, "generate_Ku failed"));
+    }
+    Ok(())
+}
+
+#[inline]
+fn snmp_errno() -> i32 {
+    unsafe { netsnmp_sys::snmp_errno }
+}
@@ -0,0 +1,325 @@
+//! Non-blocking trap / notification listener.
+//!
+//! [`TrapListener`] binds net-snmp's notification receiver transport and reports
+//! incoming traps over an [`mpsc`] channel without owning a blocking thread.
+//! It exposes the raw file descriptors and timeout net-snmp wants watched
+//! (`snmp_select_info`) so the listener can be folded into an external
+//! epoll/poll reactor, or driven through its own internal epoll via
+//! [`TrapListener::poll`].
+
+use crate::{raw_to_oid, Error, Oid, Varbind};
+use netsnmp_sys_nocrypto as netsnmp_sys;
+use std::ffi::CString;
+use std::mem::MaybeUninit;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::os::raw::{c_int, c_void};
+use std::os::unix::io::RawFd;
+use std::ptr;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+/// A received SNMP trap / notification.
+#[derive(Debug, Clone)]
+pub struct Trap {
+    /// Address the trap was received from. Only IPv4 senders are decoded: when
+    /// the sender is not an IPv4 address, or net-snmp reported no address,
+    /// this is `0.0.0.0:0`.
+    pub source: SocketAddr,
+    /// Enterprise / trap OID: the `snmpTrapOID.0` varbind for v2c/v3
+    /// notifications, or the PDU enterprise field for v1 traps. `None` when the
+    /// notification carried neither.
+    pub enterprise: Option<Oid>,
+    /// Decoded variable bindings carried by the notification.
+    pub varbinds: Vec<Varbind>,
+}
+
+/// A bound trap receiver.
+///
+/// The listener does not spin its own thread: call [`TrapListener::fds`] and
+/// [`TrapListener::timeout`] to learn what net-snmp wants watched, drive the
+/// descriptors through any reactor, and feed readiness back with
+/// [`TrapListener::read`] / [`TrapListener::handle_timeout`]. Decoded traps are
+/// delivered through the channel returned by [`TrapListener::receiver`].
+pub struct TrapListener {
+    session: *mut netsnmp_sys::snmp_session,
+    rx: Receiver<Trap>,
+    // Kept alive for the lifetime of the listener: the raw pointer is handed to
+    // net-snmp as the callback `magic` argument.
+    _tx: Box<Sender<Trap>>,
+}
+
+unsafe impl Send for TrapListener {}
+
+impl TrapListener {
+    /// Bind the notification receiver to `bind_addr` (e.g. `"udp:162"` or
+    /// `"udp:0.0.0.0:162"`).
+    pub fn bind(bind_addr: &str) -> Result<TrapListener, Error> {
+        let spec = CString::new(bind_addr).map_err(Error::invalid_data)?;
+        let (tx, rx) = mpsc::channel();
+        let tx = Box::new(tx);
+        let magic = (&*tx as *const Sender<Trap>) as *mut c_void;
+        let session = unsafe {
+            let transport = netsnmp_sys::netsnmp_transport_open_server(
+                c"snmptrap".as_ptr(),
+                spec.as_ptr(),
+            );
+            if transport.is_null() {
+                return Err(Error::failed("unable to open trap transport"));
+            }
+            let mut session: netsnmp_sys::snmp_session = std::mem::zeroed();
+            netsnmp_sys::snmp_sess_init(&mut session);
+            session.peername = ptr::null_mut();
+            session.callback = Some(trap_callback);
+            session.callback_magic = magic;
+            let handle = netsnmp_sys::snmp_add(
+                &mut session,
+                transport,
+                None,
+                None,
+            );
+            if handle.is_null() {
+                netsnmp_sys::snmp_close(&mut session);
+                return Err(Error::ffi(
+                    unsafe_snmp_errno(),
+                    "unable to register trap session",
+                ));
+            }
+            handle
+        };
+        Ok(TrapListener {
+            session,
+            rx,
+            _tx: tx,
+        })
+    }
+
+    /// Receiver side of the trap channel. Each decoded [`Trap`] is pushed here
+    /// as it is parsed during [`TrapListener::read`].
+    #[inline]
+    pub fn receiver(&self) -> &Receiver<Trap> {
+        &self.rx
+    }
+
+    /// The set of file descriptors net-snmp currently wants watched for
+    /// readability, as reported by `snmp_select_info`.
+    pub fn fds(&self) -> Vec<RawFd> {
+        let (fds, _) = self.select_info();
+        fds
+    }
+
+    /// The timeout after which [`TrapListener::handle_timeout`] must be called
+    /// so retransmits fire, or `None` when net-snmp has no pending timer.
+    pub fn timeout(&self) -> Option<Duration> {
+        let (_, timeout) = self.select_info();
+        timeout
+    }
+
+    fn select_info(&self) -> (Vec<RawFd>, Option<Duration>) {
+        unsafe {
+            let mut numfds: c_int = 0;
+            let mut fdset: libc::fd_set = std::mem::zeroed();
+            libc::FD_ZERO(&mut fdset);
+            let mut timeout = libc::timeval {
+                tv_sec: 0,
+                tv_usec: 0,
+            };
+            let mut block: c_int = 1;
+            // `snmp_select_info` fills the fd_set/timeout for every active
+            // session; `block == 0` means the timeout value is meaningful.
+            netsnmp_sys::snmp_select_info(
+                &mut numfds,
+                &mut fdset,
+                &mut timeout,
+                &mut block,
+            );
+            let mut fds = Vec::new();
+            for fd in 0..numfds {
+                if libc::FD_ISSET(fd, &fdset) {
+                    fds.push(fd);
+                }
+            }
+            let timeout = if block == 0 {
+                Some(Duration::new(
+                    timeout.tv_sec as u64,
+                    (timeout.tv_usec * 1000) as u32,
+                ))
+            } else {
+                None
+            };
+            (fds, timeout)
+        }
+    }
+
+    /// Service the descriptors that became readable. Traps parsed here are
+    /// delivered through [`TrapListener::receiver`].
+    pub fn read(&self, ready: &[RawFd]) {
+        unsafe {
+            let mut fdset: libc::fd_set = std::mem::zeroed();
+            libc::FD_ZERO(&mut fdset);
+            for &fd in ready {
+                libc::FD_SET(fd, &mut fdset);
+            }
+            netsnmp_sys::snmp_read(&mut fdset);
+        }
+    }
+
+    /// Fire any pending net-snmp timers (retransmits). Call this when the
+    /// [`TrapListener::timeout`] has elapsed without readable descriptors.
+    pub fn handle_timeout(&self) {
+        unsafe {
+            netsnmp_sys::snmp_timeout();
+        }
+    }
+
+    /// Drive one iteration of an internal epoll loop: wait up to `max_wait`
+    /// (bounded by net-snmp's own timeout) for readable descriptors, service
+    /// them, and fire timers if nothing arrived. Any traps decoded during the
+    /// iteration are left queued for the caller to drain through
+    /// [`TrapListener::receiver`].
+    pub fn poll(&self, max_wait: Option<Duration>) -> Result<(), Error> {
+        let (fds, snmp_timeout) = self.select_info();
+        let wait = match (max_wait, snmp_timeout) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (Some(a), None) => Some(a),
+            (None, Some(b)) => Some(b),
+            (None, None) => None,
+        };
+        let epfd = unsafe { libc::epoll_create1(0) };
+        if epfd < 0 {
+            return Err(Error::failed("epoll_create1 failed"));
+        }
+        let ready = unsafe {
+            for &fd in &fds {
+                let mut ev = libc::epoll_event {
+                    events: libc::EPOLLIN as u32,
+                    u64: fd as u64,
+                };
+                libc::epoll_ctl(epfd, libc::EPOLL_CTL_ADD, fd, &mut ev);
+            }
+            let timeout_ms = wait
+                .map(|d| i32::try_from(d.as_millis()).unwrap_or(i32::MAX))
+                .unwrap_or(-1);
+            let mut events: [MaybeUninit<libc::epoll_event>; 16] =
+                MaybeUninit::uninit().assume_init();
+            let n = libc::epoll_wait(
+                epfd,
+                events.as_mut_ptr().cast(),
+                events.len() as c_int,
+                timeout_ms,
+            );
+            let mut ready = Vec::new();
+            if n > 0 {
+                for ev in events.iter().take(n as usize) {
+                    ready.push(ev.assume_init().u64 as RawFd);
+                }
+            }
+            ready
+        };
+        unsafe {
+            libc::close(epfd);
+        }
+        if ready.is_empty() {
+            self.handle_timeout();
+        } else {
+            self.read(&ready);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for TrapListener {
+    fn drop(&mut self) {
+        unsafe {
+            netsnmp_sys::snmp_close(self.session);
+        }
+    }
+}
+
+/// C callback invoked by net-snmp for each received notification PDU.
+extern "C" fn trap_callback(
+    _operation: c_int,
+    _session: *mut netsnmp_sys::snmp_session,
+    _reqid: c_int,
+    pdu: *mut netsnmp_sys::snmp_pdu,
+    magic: *mut c_void,
+) -> c_int {
+    if pdu.is_null() || magic.is_null() {
+        return 1;
+    }
+    let tx = unsafe { &*(magic as *const Sender<Trap>) };
+    if let Some(trap) = unsafe { parse_trap(pdu) } {
+        // A closed receiver simply drops the trap; the session stays bound.
+        let _ = tx.send(trap);
+    }
+    1
+}
+
+unsafe fn parse_trap(pdu: *mut netsnmp_sys::snmp_pdu) -> Option<Trap> {
+    let mut trap_oid = None;
+    let mut varbinds = Vec::new();
+    let mut var = (*pdu).variables;
+    while !var.is_null() {
+        let name = std::slice::from_raw_parts((*var).name, (*var).name_length);
+        let oid = raw_to_oid(name).ok()?;
+        let value = crate::session::decode_varbind(var).ok()?;
+        // `snmpTrapOID.0` (1.3.6.1.6.3.1.1.4.1.0) carries the notification OID.
+        if is_trap_oid(&oid) {
+            if let Varbind::Oid(oid) = &value {
+                trap_oid = Some(oid.clone());
+            }
+        }
+        varbinds.push(value);
+        var = (*var).next_variable;
+    }
+    // v2c/v3 report the trap OID as a varbind; v1 carries the enterprise OID in
+    // the PDU itself, so fall back to it when no `snmpTrapOID.0` was present.
+    let enterprise = trap_oid.or_else(|| pdu_enterprise(pdu));
+    let source = pdu_source(pdu).unwrap_or_else(|| {
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0)
+    });
+    Some(Trap {
+        source,
+        enterprise,
+        varbinds,
+    })
+}
+
+/// Extract the v1 enterprise OID from the PDU, when present.
+unsafe fn pdu_enterprise(pdu: *mut netsnmp_sys::snmp_pdu) -> Option<Oid> {
+    let enterprise = (*pdu).enterprise;
+    let len = (*pdu).enterprise_length;
+    if enterprise.is_null() || len == 0 {
+        return None;
+    }
+    let slice = std::slice::from_raw_parts(enterprise, len);
+    raw_to_oid(slice).ok()
+}
+
+/// Extract the source address from the PDU transport data, when present.
+unsafe fn pdu_source(pdu: *mut netsnmp_sys::snmp_pdu) -> Option<SocketAddr> {
+    let data = (*pdu).transport_data;
+    let len = (*pdu).transport_data_length as usize;
+    if data.is_null() || len < std::mem::size_of::<libc::sockaddr_in>() {
+        return None;
+    }
+    let sa = &*(data as *const libc::sockaddr_in);
+    if sa.sin_family as i32 != libc::AF_INET {
+        return None;
+    }
+    let ip = Ipv4Addr::from(u32::from_be(sa.sin_addr.s_addr));
+    let port = u16::from_be(sa.sin_port);
+    Some(SocketAddr::new(IpAddr::V4(ip), port))
+}
+
+#[inline]
+fn is_trap_oid(oid: &Oid) -> bool {
+    const SNMP_TRAP_OID: [u64; 11] = [1, 3, 6, 1, 6, 3, 1, 1, 4, 1, 0];
+    oid.iter_bigint()
+        .map(|v| u64::try_from(v).unwrap_or(u64::MAX))
+        .eq(SNMP_TRAP_OID.iter().copied())
+}
+
+#[inline]
+fn unsafe_snmp_errno() -> i32 {
+    unsafe { netsnmp_sys::snmp_errno }
+}